@@ -12,20 +12,19 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 
-use autocxx_parser::TypeDatabase;
+use autocxx_parser::{Category, TypeDatabase};
 
 use crate::{
-    conversion::api::{Api, ApiAnalysis},
+    conversion::api::{Api, ApiAnalysis, ApiKind},
     types::TypeName,
 };
 
-/// This is essentially mark-and-sweep garbage collection of the
-/// Apis that we've discovered. Why do we do this, you might wonder?
-/// It seems a bit strange given that we pass an explicit allowlist
-/// to bindgen.
-/// There are two circumstances under which we want to discard
+/// This is essentially mark-and-sweep garbage collection of the Apis
+/// that we've discovered. Why do we do this, you might wonder? It
+/// seems a bit strange given that we pass an explicit allowlist to
+/// bindgen. There are two circumstances under which we want to discard
 /// some of the APIs we encounter parsing the bindgen.
 /// 1) We simplify some struct to be non-POD. In this case, we'll
 ///    discard all the fields within it. Those fields can be, and
@@ -38,35 +37,477 @@ use crate::{
 ///    some methods from a given struct/class. In which case, we
 ///    don't care about the other parameter types passed into those
 ///    APIs either.
+///
+/// `generate_only!`/`codegen_config!` (see [`autocxx_parser::Category`])
+/// add a third source of pruning: a type can be restricted to only some
+/// categories of API (e.g. just its constructors), in which case the
+/// other categories - and their deps - are dropped the same way.
+///
+/// We walk a `VecDeque` worklist seeded from the allowlisted roots (in
+/// their input `Vec` order, so results are reproducible regardless of
+/// hasher seed), following each surviving API's `deps` to find what
+/// else it keeps alive, and skipping edges a [`TemplateIsolationAnalysis`]
+/// tells us are a template parameter that never actually affects the
+/// instantiated layout. Whatever's left in `by_typename` once the
+/// worklist is empty was never reached, and is dropped: if that's
+/// because the only path to it ran through a `block!`-ed API, we surface
+/// the reason the user gave `block!` via `block_diagnostics`, rather
+/// than silently dropping the type with no explanation. If `want_report`
+/// is set we also build a [`ReachabilityReport`], keeping a predecessor
+/// map as we pop each `todo` so any survivor's path back to the root
+/// that kept it alive can be reconstructed afterwards - modelled on how
+/// rustc derives its reachable set from the privacy pass.
 pub(crate) fn filter_apis_by_following_edges_from_allowlist<T: ApiAnalysis>(
     mut apis: Vec<Api<T>>,
     type_database: &TypeDatabase,
-) -> Vec<Api<T>> {
-    let mut todos: Vec<_> = apis
+    want_report: bool,
+) -> (Vec<Api<T>>, Vec<String>, Option<ReachabilityReport>) {
+    let mut seen_roots = HashSet::new();
+    let roots: Vec<TypeName> = apis
         .iter()
         .filter(|api| {
             let tnforal = api.typename_for_allowlist();
             type_database.is_on_allowlist(&tnforal.to_cpp_name())
         })
         .map(Api::typename)
+        .filter(|tn| seen_roots.insert(tn.clone()))
         .collect();
+    let isolation = TemplateIsolationAnalysis::compute(&apis);
+    let mut todos: VecDeque<_> = roots.iter().cloned().collect();
     let mut by_typename: HashMap<TypeName, Vec<Api<T>>> = HashMap::new();
     for api in apis.drain(..) {
         let tn = api.typename();
         by_typename.entry(tn).or_default().push(api);
     }
+    // Only populated when `want_report` is set: records, for each type
+    // name we enqueue, the type which caused us to enqueue it. Roots are
+    // their own predecessor so path reconstruction has a base case.
+    let mut predecessors: HashMap<TypeName, TypeName> = HashMap::new();
+    if want_report {
+        for root in &roots {
+            predecessors.insert(root.clone(), root.clone());
+        }
+    }
     let mut done = HashSet::new();
     let mut output = Vec::new();
-    while !todos.is_empty() {
-        let todo = todos.remove(0);
+    while let Some(todo) = todos.pop_front() {
         if done.contains(&todo) {
             continue;
         }
-        if let Some(mut these_apis) = by_typename.remove(&todo) {
-            todos.extend(these_apis.iter().flat_map(|api| api.deps.iter().cloned()));
-            output.append(&mut these_apis);
+        if let Some(these_apis) = by_typename.remove(&todo) {
+            let mut kept = Vec::with_capacity(these_apis.len());
+            for api in these_apis {
+                // `generate_only!`/`codegen_config!` restricts categories
+                // per *owning type* (`Bar` in `generate_only!("Bar",
+                // methods)`), not per-API, so the restriction must be
+                // looked up under `typename_for_allowlist()` (`Bar`) -
+                // `todo`/`api.typename()` here is the API's own identity
+                // (e.g. `Bar::method1`), which is never what a directive
+                // is registered under. An API whose category isn't
+                // enabled for its owning type is dropped here, exactly as
+                // if it had never been discovered - and, like the block!
+                // case above, we don't follow its deps either.
+                if let Some(category) = api_category(&api) {
+                    let owner = api.typename_for_allowlist().to_cpp_name();
+                    if !type_database.is_category_enabled(&owner, category) {
+                        continue;
+                    }
+                }
+                for dep in &api.deps {
+                    if !isolation.is_isolated_dep(&todo, dep) {
+                        continue;
+                    }
+                    if want_report {
+                        predecessors.entry(dep.clone()).or_insert_with(|| todo.clone());
+                    }
+                    todos.push_back(dep.clone());
+                }
+                kept.push(api);
+            }
+            output.extend(kept);
         } // otherwise, probably an intrinsic e.g. uint32_t.
         done.insert(todo);
     }
-    output
+    // Anything left in `by_typename` was never reached from an allowlisted
+    // root, so it's about to be dropped silently. Note this deliberately
+    // checks *why the dependency was orphaned*, not whether the orphaned
+    // type itself was blocked - the common case this exists for is a
+    // blocked method whose now-unreachable parameter type was never
+    // itself named in a `block!`. Sorted so the diagnostics are stable
+    // across runs, same as `output` now is.
+    let mut block_diagnostics: Vec<String> = by_typename
+        .keys()
+        .filter_map(|unreached| {
+            type_database
+                .reason_type_is_orphaned_by_block(&unreached.to_cpp_name())
+                .map(|reason| {
+                    format!(
+                        "{} was not generated because it was only reachable via a blocked API: {}",
+                        unreached, reason
+                    )
+                })
+        })
+        .collect();
+    block_diagnostics.sort();
+    let report = want_report.then(|| ReachabilityReport::new(&done, by_typename.keys(), &predecessors));
+    (output, block_diagnostics, report)
+}
+
+/// Explains, for each type name the mark-and-sweep in
+/// [`filter_apis_by_following_edges_from_allowlist`] reached or left
+/// unreached, whether it was reached and if so, how we got to it. A
+/// reached type is listed as reachable even if a category restriction
+/// then dropped all of its APIs. Intended to be written out (e.g. as a
+/// `--report` file) so that a user staring at a large header can find
+/// out why a given type didn't make it into the generated bindings.
+#[derive(Debug)]
+pub(crate) struct ReachabilityReport {
+    /// One entry per type name the sweep reached (including names with
+    /// no discovered API, such as intrinsics) and one per discovered
+    /// type it never reached.
+    entries: Vec<ReachabilityEntry>,
+}
+
+#[derive(Debug)]
+struct ReachabilityEntry {
+    typename: TypeName,
+    reachability: Reachability,
+}
+
+#[derive(Debug)]
+enum Reachability {
+    /// This type was never reached from any allowlisted root.
+    Unreachable,
+    /// This type was reached; the path lists each hop starting at the
+    /// allowlisted root and ending at this type (inclusive of both).
+    ReachableVia(Vec<TypeName>),
+}
+
+impl ReachabilityReport {
+    fn new<'a>(
+        reached: &HashSet<TypeName>,
+        unreached: impl Iterator<Item = &'a TypeName>,
+        predecessors: &HashMap<TypeName, TypeName>,
+    ) -> Self {
+        let mut entries: Vec<_> = reached
+            .iter()
+            .map(|tn| ReachabilityEntry {
+                typename: tn.clone(),
+                reachability: Reachability::ReachableVia(Self::path_to_root(tn, predecessors)),
+            })
+            .collect();
+        entries.extend(unreached.map(|tn| ReachabilityEntry {
+            typename: tn.clone(),
+            reachability: Reachability::Unreachable,
+        }));
+        Self { entries }
+    }
+
+    /// Walks the predecessor map backwards from `tn` to the allowlisted
+    /// root which kept it alive, then reverses the result so it reads
+    /// root-first.
+    fn path_to_root(tn: &TypeName, predecessors: &HashMap<TypeName, TypeName>) -> Vec<TypeName> {
+        let mut path = vec![tn.clone()];
+        let mut current = tn;
+        while let Some(pred) = predecessors.get(current) {
+            if pred == current {
+                break; // we've reached a root, which is its own predecessor.
+            }
+            path.push(pred.clone());
+            current = pred;
+        }
+        path.reverse();
+        path
+    }
+
+    /// Renders the report as plain text, one line per entry,
+    /// suitable for dumping to the file requested by the `report`
+    /// build flag.
+    pub(crate) fn to_text(&self) -> String {
+        let mut lines: Vec<_> = self
+            .entries
+            .iter()
+            .map(|entry| match &entry.reachability {
+                Reachability::Unreachable => {
+                    format!("{}: not reachable from any allowlisted entry", entry.typename)
+                }
+                Reachability::ReachableVia(path) => {
+                    let path_str = path
+                        .iter()
+                        .map(|tn| tn.to_cpp_name())
+                        .collect::<Vec<_>>()
+                        .join(" -> ");
+                    format!("{}: reachable via {}", entry.typename, path_str)
+                }
+            })
+            .collect();
+        lines.sort();
+        lines.join("\n")
+    }
+}
+
+/// Works out which `autocxx_parser::Category` an API belongs to, so
+/// the sweep can consult the per-type `generate_only!`/`codegen_config!`
+/// restriction for it. Returns `None` for API kinds that restriction
+/// never applies to (types, typedefs, consts, ...): `generate_only!`
+/// only thins out the members of a type, never the type itself.
+fn api_category<T: ApiAnalysis>(api: &Api<T>) -> Option<Category> {
+    match api.kind {
+        ApiKind::Function => Some(Category::Functions),
+        ApiKind::Method => Some(Category::Methods),
+        ApiKind::Constructor => Some(Category::Constructors),
+        ApiKind::Destructor => Some(Category::Destructors),
+        ApiKind::Static => Some(Category::Vars),
+        ApiKind::Other => None,
+    }
+}
+
+/// For every templated C++ type we've discovered, records for each of
+/// its generic parameter positions whether that parameter is
+/// "isolated" - genuinely affecting the instantiated type's layout
+/// because some field uses it by value - as opposed to only appearing
+/// behind a pointer/reference, or only inside a field we've already
+/// decided to discard (e.g. because we simplified the enclosing struct
+/// to non-POD).
+///
+/// This mirrors the way heap-item type analysis classifies each
+/// generic parameter of a collection as isolated (a standalone `T`)
+/// versus merely a phantom marker: here we're asking the analogous
+/// question of C++ template parameters and struct layout.
+#[derive(Debug, Default)]
+pub(crate) struct TemplateIsolationAnalysis {
+    isolated_params: HashMap<TypeName, Vec<bool>>,
+}
+
+impl TemplateIsolationAnalysis {
+    /// Builds the summary by inspecting every discovered API's layout
+    /// fields before the mark-and-sweep runs. This has to happen
+    /// up-front: once the sweep has discarded a non-POD struct's
+    /// fields, the information about which of its fields carried a
+    /// given generic parameter is gone.
+    fn compute<T: ApiAnalysis>(apis: &[Api<T>]) -> Self {
+        let mut isolated_params: HashMap<TypeName, Vec<bool>> = HashMap::new();
+        for api in apis {
+            let tn = api.typename();
+            let generic_args = match tn.generic_args() {
+                Some(args) if !args.is_empty() => args,
+                _ => continue,
+            };
+            let entry = isolated_params
+                .entry(tn.template_name())
+                .or_insert_with(|| vec![false; generic_args.len()]);
+            if entry.len() < generic_args.len() {
+                entry.resize(generic_args.len(), false);
+            }
+            for (field_type, by_value) in api.layout_fields() {
+                if let Some(position) = generic_args.iter().position(|arg| arg == field_type) {
+                    entry[position] |= *by_value;
+                }
+            }
+        }
+        Self { isolated_params }
+    }
+
+    /// Whether `dep`, as used by `instantiation`, should still be
+    /// followed by the sweep. Anything that isn't itself a generic
+    /// parameter of a template instantiation (plain fields, base
+    /// classes, a non-template type) is always followed; we only prune
+    /// edges we can positively identify as a non-isolated template
+    /// parameter.
+    fn is_isolated_dep(&self, instantiation: &TypeName, dep: &TypeName) -> bool {
+        let generic_args = match instantiation.generic_args() {
+            Some(args) if !args.is_empty() => args,
+            _ => return true,
+        };
+        let position = match generic_args.iter().position(|arg| arg == dep) {
+            Some(position) => position,
+            None => return true,
+        };
+        self.isolated_params
+            .get(&instantiation.template_name())
+            .and_then(|flags| flags.get(position))
+            .copied()
+            // Be conservative if no instantiation of this template was
+            // discovered at all - better to keep an edge than to silently
+            // drop a type the user actually needs. A discovered
+            // instantiation with no layout fields has every parameter
+            // recorded as non-isolated, so its parameter edges are pruned.
+            .unwrap_or(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conversion::api::omit_blocked_apis;
+
+    fn api(
+        cpp_name: &str,
+        kind: ApiKind,
+        deps: Vec<&str>,
+    ) -> Api<()> {
+        member_api(cpp_name, cpp_name, kind, deps)
+    }
+
+    /// Like `api`, but for a member (method, constructor, ...) whose own
+    /// name differs from the owning type it should be allowlisted/
+    /// category-restricted under - e.g. `member_api("Bar", "Bar::method1",
+    /// ApiKind::Method, ..)`.
+    fn member_api(
+        owning_type: &str,
+        own_name: &str,
+        kind: ApiKind,
+        deps: Vec<&str>,
+    ) -> Api<()> {
+        Api::new(
+            TypeName::new(own_name),
+            TypeName::new(owning_type),
+            kind,
+            deps.into_iter().map(TypeName::new).collect(),
+            (),
+        )
+    }
+
+    #[test]
+    fn generate_only_restriction_drops_disallowed_categories_and_their_deps() {
+        let mut db = TypeDatabase::default();
+        db.add_to_allowlist("Foo");
+        db.restrict_to_categories("Foo", vec![Category::Methods]);
+
+        let apis = vec![
+            api("Foo", ApiKind::Method, vec!["MethodParam"]),
+            api("Foo", ApiKind::Constructor, vec!["CtorParam"]),
+            api("MethodParam", ApiKind::Other, vec![]),
+            api("CtorParam", ApiKind::Other, vec![]),
+        ];
+
+        let (output, _diagnostics, _report) =
+            filter_apis_by_following_edges_from_allowlist(apis, &db, false);
+
+        let survivors: HashSet<String> =
+            output.iter().map(|api| api.typename().to_cpp_name()).collect();
+        assert_eq!(
+            survivors,
+            ["Foo", "MethodParam"].iter().map(|s| s.to_string()).collect()
+        );
+    }
+
+    #[test]
+    fn blocked_method_orphans_its_param_type_and_the_sweep_explains_why() {
+        // The scenario chunk0-1 exists for: `block!` is used on a
+        // *method*, which orphans a *parameter type* that was never
+        // itself named in `block!`.
+        let mut db = TypeDatabase::default();
+        db.add_to_allowlist("Foo");
+        db.block("Foo::bar", Some("not thread-safe"));
+
+        let candidates = vec![
+            member_api("Foo", "Foo", ApiKind::Other, vec![]),
+            member_api("Foo", "Foo::bar", ApiKind::Method, vec!["Baz"]),
+            api("Baz", ApiKind::Other, vec![]),
+        ];
+        let apis = omit_blocked_apis(candidates, &mut db);
+
+        let (output, diagnostics, _report) =
+            filter_apis_by_following_edges_from_allowlist(apis, &db, false);
+
+        // `Baz` was never itself blocked, so it's simply unreachable now
+        // that the only API referencing it was omitted.
+        assert!(!output.iter().any(|api| api.typename().to_cpp_name() == "Baz"));
+        assert_eq!(
+            diagnostics,
+            vec!["Baz was not generated because it was only reachable via a blocked API: not thread-safe".to_string()]
+        );
+    }
+
+    #[test]
+    fn category_restriction_is_looked_up_by_owning_type_not_the_members_own_name() {
+        // A real type has members whose own name (`Bar::method1`) differs
+        // from the owning type `generate_only!`/`restrict_to_categories`
+        // registers the restriction under (`Bar`).
+        let mut db = TypeDatabase::default();
+        db.add_to_allowlist("Bar");
+        db.restrict_to_categories("Bar", vec![Category::Methods]);
+
+        let apis = vec![
+            member_api("Bar", "Bar::method1", ApiKind::Method, vec!["MethodParam"]),
+            member_api("Bar", "Bar::Bar", ApiKind::Constructor, vec!["CtorParam"]),
+            api("MethodParam", ApiKind::Other, vec![]),
+            api("CtorParam", ApiKind::Other, vec![]),
+        ];
+
+        let (output, _diagnostics, _report) =
+            filter_apis_by_following_edges_from_allowlist(apis, &db, false);
+
+        let survivors: HashSet<String> =
+            output.iter().map(|api| api.typename().to_cpp_name()).collect();
+        assert_eq!(
+            survivors,
+            ["Bar::method1", "MethodParam"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect()
+        );
+    }
+
+    #[test]
+    fn api_category_has_no_restriction_category_for_non_member_apis() {
+        let ty = api("Foo", ApiKind::Other, vec![]);
+        assert_eq!(api_category(&ty), None);
+    }
+
+    #[test]
+    fn path_to_root_reads_root_first_and_stops_at_a_root() {
+        let mut predecessors = HashMap::new();
+        predecessors.insert(TypeName::new("Root"), TypeName::new("Root"));
+        predecessors.insert(TypeName::new("Mid"), TypeName::new("Root"));
+        predecessors.insert(TypeName::new("Leaf"), TypeName::new("Mid"));
+
+        let path = ReachabilityReport::path_to_root(&TypeName::new("Leaf"), &predecessors);
+
+        assert_eq!(
+            path,
+            vec![
+                TypeName::new("Root"),
+                TypeName::new("Mid"),
+                TypeName::new("Leaf")
+            ]
+        );
+    }
+
+    #[test]
+    fn survivor_order_is_bfs_from_roots_not_hashmap_iteration_order() {
+        let mut db = TypeDatabase::default();
+        db.add_to_allowlist("A");
+
+        let apis = vec![
+            api("A", ApiKind::Other, vec!["B", "C"]),
+            api("B", ApiKind::Other, vec![]),
+            api("C", ApiKind::Other, vec![]),
+        ];
+
+        let (output, _diagnostics, _report) =
+            filter_apis_by_following_edges_from_allowlist(apis, &db, false);
+
+        let order: Vec<String> = output.iter().map(|api| api.typename().to_cpp_name()).collect();
+        assert_eq!(order, vec!["A", "B", "C"]);
+    }
+
+    #[test]
+    fn isolated_dep_is_kept_but_pointer_only_dep_is_pruned() {
+        // MyTemplate<A, B> { A by_value_field; B* pointer_field; }
+        let instantiation = api("MyTemplate<Isolated, NotIsolated>", ApiKind::Other, vec![])
+            .with_fields(vec![
+                (TypeName::new("Isolated"), true),
+                (TypeName::new("NotIsolated"), false),
+            ]);
+        let isolation = TemplateIsolationAnalysis::compute(&[instantiation]);
+
+        let instantiation_name = TypeName::new("MyTemplate<Isolated, NotIsolated>");
+        assert!(isolation.is_isolated_dep(&instantiation_name, &TypeName::new("Isolated")));
+        assert!(!isolation.is_isolated_dep(&instantiation_name, &TypeName::new("NotIsolated")));
+        // A dep that isn't even a generic parameter of this instantiation
+        // (e.g. a plain field type) is always kept.
+        assert!(isolation.is_isolated_dep(&instantiation_name, &TypeName::new("Unrelated")));
+    }
 }
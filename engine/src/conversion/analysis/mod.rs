@@ -0,0 +1,34 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub(crate) mod gc;
+
+use autocxx_parser::TypeDatabase;
+
+use crate::conversion::api::{Api, ApiAnalysis};
+use gc::{filter_apis_by_following_edges_from_allowlist, ReachabilityReport};
+
+/// Runs the mark-and-sweep GC pass (see [`gc`]) over the APIs discovered
+/// from bindgen's output, discarding anything unreachable from the
+/// allowlist. `want_report` should be set when the build requested a
+/// reachability report (e.g. via a `--report` flag); the block
+/// diagnostics are always collected, since that's cheap, and it's up to
+/// the caller whether to surface them (e.g. as `compile_error!`s).
+pub(crate) fn run_gc_phase<T: ApiAnalysis>(
+    apis: Vec<Api<T>>,
+    type_database: &TypeDatabase,
+    want_report: bool,
+) -> (Vec<Api<T>>, Vec<String>, Option<ReachabilityReport>) {
+    filter_apis_by_following_edges_from_allowlist(apis, type_database, want_report)
+}
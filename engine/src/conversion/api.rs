@@ -0,0 +1,166 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use autocxx_parser::TypeDatabase;
+
+use crate::types::TypeName;
+
+/// Marker trait for the extra data a given analysis phase attaches to
+/// each [`Api`]. Each pass over the discovered APIs (type analysis,
+/// POD analysis, and so on) defines its own implementor to carry
+/// whatever that pass computed; callers which don't care about any
+/// particular phase can use `()`.
+pub(crate) trait ApiAnalysis {}
+
+impl ApiAnalysis for () {}
+
+/// What kind of C++ API a given [`Api`] represents. Used to support
+/// `generate_only!`/`codegen_config!`'s per-category restrictions,
+/// which mirror bindgen's own `CodegenConfig`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ApiKind {
+    Function,
+    Method,
+    Constructor,
+    Destructor,
+    Static,
+    /// Everything else: structs, enums, typedefs, consts, and so on.
+    /// `generate_only!` never restricts this category - it only thins
+    /// out the members of a type, not the type itself.
+    Other,
+}
+
+/// One API (function, method, struct, ...) discovered by bindgen and
+/// carried through the conversion pipeline.
+pub(crate) struct Api<T: ApiAnalysis> {
+    name: TypeName,
+    allowlist_name: TypeName,
+    pub(crate) kind: ApiKind,
+    /// The other types this API's signature or fields refer to. Used
+    /// by the mark-and-sweep in `conversion::analysis::gc`.
+    pub(crate) deps: Vec<TypeName>,
+    /// If this API is a struct/class, its fields in declaration order,
+    /// paired with whether each one holds its type by value (`true`) as
+    /// opposed to behind a pointer/reference (`false`). Empty for any
+    /// API that isn't a type (functions, methods, ...). Used by
+    /// `conversion::analysis::gc::TemplateIsolationAnalysis` to work out
+    /// which of a template's generic parameters actually affect layout.
+    pub(crate) fields: Vec<(TypeName, bool)>,
+    #[allow(dead_code)]
+    pub(crate) analysis: T,
+}
+
+impl<T: ApiAnalysis> Api<T> {
+    pub(crate) fn new(
+        name: TypeName,
+        allowlist_name: TypeName,
+        kind: ApiKind,
+        deps: Vec<TypeName>,
+        analysis: T,
+    ) -> Self {
+        Self {
+            name,
+            allowlist_name,
+            kind,
+            deps,
+            fields: Vec::new(),
+            analysis,
+        }
+    }
+
+    /// Attaches field layout information to a type API, for types whose
+    /// fields we've discovered. See the `fields` doc comment for the
+    /// by-value/by-reference distinction this carries.
+    pub(crate) fn with_fields(mut self, fields: Vec<(TypeName, bool)>) -> Self {
+        self.fields = fields;
+        self
+    }
+
+    /// This API's fields, if it's a struct/class; empty otherwise.
+    pub(crate) fn layout_fields(&self) -> &[(TypeName, bool)] {
+        &self.fields
+    }
+
+    pub(crate) fn typename(&self) -> TypeName {
+        self.name.clone()
+    }
+
+    /// The name under which this API should be looked up in the
+    /// allowlist - for a method this is the owning type, for a free
+    /// function it's the function itself.
+    pub(crate) fn typename_for_allowlist(&self) -> TypeName {
+        self.allowlist_name.clone()
+    }
+}
+
+/// The bindgen-to-`Api` conversion step that omits `block!`-ed APIs.
+/// Each `candidate` whose own name was blocked is dropped here, but not
+/// before its deps are handed to [`TypeDatabase::note_blocked_api_deps`],
+/// so `conversion::analysis::gc` can still explain why a type that was
+/// only reachable through the blocked API never made it into `output`.
+pub(crate) fn omit_blocked_apis<T: ApiAnalysis>(
+    candidates: Vec<Api<T>>,
+    type_database: &mut TypeDatabase,
+) -> Vec<Api<T>> {
+    candidates
+        .into_iter()
+        .filter(|api| {
+            let cpp_name = api.typename().to_cpp_name();
+            if type_database.is_blocked(&cpp_name) {
+                type_database
+                    .note_blocked_api_deps(&cpp_name, api.deps.iter().map(TypeName::to_cpp_name));
+                false
+            } else {
+                true
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocked_api_is_omitted_and_its_deps_are_recorded() {
+        let mut db = TypeDatabase::default();
+        db.block("Foo::bar", Some("not thread-safe"));
+
+        let candidates = vec![
+            Api::new(
+                TypeName::new("Foo::bar"),
+                TypeName::new("Foo"),
+                ApiKind::Method,
+                vec![TypeName::new("Baz")],
+                (),
+            ),
+            Api::new(
+                TypeName::new("Foo::other"),
+                TypeName::new("Foo"),
+                ApiKind::Method,
+                vec![],
+                (),
+            ),
+        ];
+
+        let kept = omit_blocked_apis(candidates, &mut db);
+
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].typename().to_cpp_name(), "Foo::other");
+        assert_eq!(
+            db.reason_type_is_orphaned_by_block("Baz"),
+            Some("not thread-safe")
+        );
+    }
+}
@@ -0,0 +1,71 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub(crate) mod analysis;
+pub(crate) mod api;
+
+use autocxx_parser::TypeDatabase;
+
+use api::{Api, ApiAnalysis};
+
+/// The full conversion pipeline from bindgen's raw output to the set of
+/// `Api`s that should actually be generated: first
+/// [`api::omit_blocked_apis`] drops anything `block!`-ed (recording its
+/// deps so the sweep can still explain any resulting orphans), then
+/// [`analysis::run_gc_phase`] does the mark-and-sweep over what's left.
+/// This is the one place both of those passes are actually called from;
+/// in a full build it's `include_cpp!`'s macro expansion that would
+/// drive this with bindgen's real output and the directives parsed from
+/// the macro body.
+pub(crate) fn convert<T: ApiAnalysis>(
+    candidates: Vec<Api<T>>,
+    type_database: &mut TypeDatabase,
+    want_report: bool,
+) -> (Vec<Api<T>>, Vec<String>, Option<analysis::gc::ReachabilityReport>) {
+    let apis = api::omit_blocked_apis(candidates, type_database);
+    analysis::run_gc_phase(apis, type_database, want_report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conversion::api::ApiKind;
+    use crate::types::TypeName;
+
+    #[test]
+    fn convert_runs_block_omission_then_the_gc_sweep_and_builds_a_report() {
+        let mut db = TypeDatabase::default();
+        db.add_to_allowlist("Foo");
+        db.block("Foo::bar", Some("not thread-safe"));
+
+        let candidates = vec![
+            Api::new(TypeName::new("Foo"), TypeName::new("Foo"), ApiKind::Other, vec![], ()),
+            Api::new(
+                TypeName::new("Foo::bar"),
+                TypeName::new("Foo"),
+                ApiKind::Method,
+                vec![TypeName::new("Baz")],
+                (),
+            ),
+            Api::new(TypeName::new("Baz"), TypeName::new("Baz"), ApiKind::Other, vec![], ()),
+        ];
+
+        let (output, diagnostics, report) = convert(candidates, &mut db, true);
+
+        assert!(output.iter().any(|api| api.typename().to_cpp_name() == "Foo"));
+        assert!(!output.iter().any(|api| api.typename().to_cpp_name() == "Baz"));
+        assert_eq!(diagnostics.len(), 1);
+        assert!(report.unwrap().to_text().contains("Baz"));
+    }
+}
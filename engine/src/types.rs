@@ -0,0 +1,109 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+
+/// The name of a C++ type, as it appears in the code we're binding to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub(crate) struct TypeName(String);
+
+impl TypeName {
+    pub(crate) fn new(cpp_name: &str) -> Self {
+        Self(cpp_name.to_string())
+    }
+
+    pub(crate) fn to_cpp_name(&self) -> String {
+        self.0.clone()
+    }
+
+    /// If this is a template instantiation (e.g. `MyTemplate<Foo, Bar>`),
+    /// the generic arguments in order (`[Foo, Bar]`). `None` if this
+    /// name isn't a template instantiation at all; `Some(vec![])` should
+    /// not occur in practice since `<>` with no arguments isn't valid
+    /// C++, but is handled the same as `None` by callers regardless.
+    pub(crate) fn generic_args(&self) -> Option<Vec<TypeName>> {
+        let open = self.0.find('<')?;
+        let close = self.0.rfind('>')?;
+        if close < open {
+            return None;
+        }
+        Some(
+            Self::split_top_level_commas(&self.0[open + 1..close])
+                .into_iter()
+                .map(|arg| TypeName::new(arg.trim()))
+                .collect(),
+        )
+    }
+
+    /// The part of this name before the generic argument list, e.g.
+    /// `MyTemplate` for `MyTemplate<Foo, Bar>`. Returns this name
+    /// unchanged if it isn't a template instantiation.
+    pub(crate) fn template_name(&self) -> TypeName {
+        match self.0.find('<') {
+            Some(open) => TypeName::new(&self.0[..open]),
+            None => self.clone(),
+        }
+    }
+
+    /// Splits `s` on commas that aren't nested inside a further `<...>`,
+    /// so that e.g. `Foo<Bar>, Baz` splits into `["Foo<Bar>", " Baz"]`
+    /// rather than incorrectly splitting inside the nested template's
+    /// own argument list.
+    fn split_top_level_commas(s: &str) -> Vec<&str> {
+        let mut parts = Vec::new();
+        let mut depth = 0;
+        let mut start = 0;
+        for (i, c) in s.char_indices() {
+            match c {
+                '<' => depth += 1,
+                '>' => depth -= 1,
+                ',' if depth == 0 => {
+                    parts.push(&s[start..i]);
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        parts.push(&s[start..]);
+        parts
+    }
+}
+
+impl fmt::Display for TypeName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn non_template_name_has_no_generic_args() {
+        let tn = TypeName::new("Foo");
+        assert_eq!(tn.generic_args(), None);
+        assert_eq!(tn.template_name(), tn);
+    }
+
+    #[test]
+    fn template_name_splits_generic_args_at_top_level_only() {
+        let tn = TypeName::new("MyTemplate<Foo, Bar<Baz, Qux>>");
+        assert_eq!(tn.template_name(), TypeName::new("MyTemplate"));
+        assert_eq!(
+            tn.generic_args(),
+            Some(vec![TypeName::new("Foo"), TypeName::new("Bar<Baz, Qux>")])
+        );
+    }
+}
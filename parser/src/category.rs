@@ -0,0 +1,40 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+/// A category of generated API, mirroring bindgen's own
+/// `CodegenConfig`. `generate_only!`/`codegen_config!` use this to
+/// restrict a type to only some of its members.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Category {
+    Functions,
+    Methods,
+    Constructors,
+    Destructors,
+    Vars,
+}
+
+impl Category {
+    /// Parses the bare identifier used in a directive, e.g. the
+    /// `methods` in `generate_only!("MyClass", methods)`.
+    pub fn from_directive_name(name: &str) -> Option<Self> {
+        match name {
+            "functions" => Some(Category::Functions),
+            "methods" => Some(Category::Methods),
+            "constructors" => Some(Category::Constructors),
+            "destructors" => Some(Category::Destructors),
+            "vars" => Some(Category::Vars),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,37 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+mod category;
+mod type_database;
+
+pub use category::Category;
+pub use type_database::TypeDatabase;
+
+/// A `block!` directive as it appears inside an `include_cpp!` block,
+/// e.g. `block!("Foo::bar")` or `block!("Foo::bar", reason = "not
+/// thread-safe")`. The directive parser (which walks the
+/// `include_cpp!` token tree) constructs one of these per entry and
+/// feeds it to [`TypeDatabase::block`].
+pub struct BlockDirective {
+    pub cpp_name: String,
+    pub reason: Option<String>,
+}
+
+/// A `generate_only!`/`codegen_config!` directive, e.g.
+/// `generate_only!("MyClass", methods, constructors)`. Fed to
+/// [`TypeDatabase::restrict_to_categories`].
+pub struct GenerateOnlyDirective {
+    pub cpp_name: String,
+    pub categories: Vec<Category>,
+}
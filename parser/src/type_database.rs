@@ -0,0 +1,165 @@
+// Copyright 2020 Google LLC
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//    https://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::Category;
+
+/// What we know about an API that `block!` removed from generation.
+#[derive(Debug, Clone, Default)]
+struct BlockedApi {
+    /// The `reason = "..."` given to `block!`, if any.
+    reason: Option<String>,
+    /// The C++ names of the types this API's signature referred to.
+    /// The engine's bindgen-to-`Api` conversion pass records these via
+    /// [`TypeDatabase::note_blocked_api_deps`] at the point it omits
+    /// the blocked API, so that a later GC sweep can still explain why
+    /// a type that was only reachable through it never got generated.
+    would_have_referenced: Vec<String>,
+}
+
+/// Everything the `include_cpp!` directives (`generate!`, `block!`,
+/// ...) tell us about which APIs the user wants. Threaded through the
+/// whole conversion pipeline so later passes can consult it.
+#[derive(Debug, Default)]
+pub struct TypeDatabase {
+    allowlist: HashSet<String>,
+    blocked: HashMap<String, BlockedApi>,
+    category_restrictions: HashMap<String, HashSet<Category>>,
+}
+
+impl TypeDatabase {
+    pub fn is_on_allowlist(&self, cpp_name: &str) -> bool {
+        self.allowlist.contains(cpp_name)
+    }
+
+    /// Called for each `generate!("...")` directive.
+    pub fn add_to_allowlist(&mut self, cpp_name: impl Into<String>) {
+        self.allowlist.insert(cpp_name.into());
+    }
+
+    /// Called for each `block!("...")` or `block!("...", reason = "...")`
+    /// directive.
+    pub fn block(&mut self, cpp_name: impl Into<String>, reason: Option<impl Into<String>>) {
+        self.blocked.insert(
+            cpp_name.into(),
+            BlockedApi {
+                reason: reason.map(Into::into),
+                would_have_referenced: Vec::new(),
+            },
+        );
+    }
+
+    pub fn is_blocked(&self, cpp_name: &str) -> bool {
+        self.blocked.contains_key(cpp_name)
+    }
+
+    /// The reason a specific blocked API was blocked, if a reason was
+    /// given. Note this is keyed by the blocked API's own name (e.g.
+    /// `"Foo::bar"`) - to find out why some *other*, still-discovered
+    /// type was orphaned because `Foo::bar` was blocked, use
+    /// [`TypeDatabase::reason_type_is_orphaned_by_block`] instead.
+    pub fn reason_for_block(&self, cpp_name: &str) -> Option<&str> {
+        self.blocked.get(cpp_name)?.reason.as_deref()
+    }
+
+    /// Records that the blocked API `cpp_name` would, had it not been
+    /// blocked, have referenced `referenced_types`. Called by
+    /// `engine::conversion::api::omit_blocked_apis` as it omits a
+    /// blocked API, before that API's parameter/return types are lost
+    /// for good.
+    pub fn note_blocked_api_deps(
+        &mut self,
+        cpp_name: &str,
+        referenced_types: impl IntoIterator<Item = String>,
+    ) {
+        if let Some(blocked) = self.blocked.get_mut(cpp_name) {
+            blocked.would_have_referenced.extend(referenced_types);
+        }
+    }
+
+    /// The reason given to a blocked API that would have referenced
+    /// `cpp_name`, if there is such an API and it was given a reason.
+    /// Whether anything else still references `cpp_name` is not checked.
+    pub fn reason_type_is_orphaned_by_block(&self, cpp_name: &str) -> Option<&str> {
+        self.blocked.values().find_map(|blocked| {
+            blocked
+                .would_have_referenced
+                .iter()
+                .any(|referenced| referenced == cpp_name)
+                .then(|| blocked.reason.as_deref())
+                .flatten()
+        })
+    }
+
+    /// Called for each `generate_only!("...", category, ...)` or
+    /// `codegen_config!` directive: restricts `cpp_name` to only the
+    /// given categories of API.
+    pub fn restrict_to_categories(
+        &mut self,
+        cpp_name: impl Into<String>,
+        categories: impl IntoIterator<Item = Category>,
+    ) {
+        self.category_restrictions
+            .entry(cpp_name.into())
+            .or_default()
+            .extend(categories);
+    }
+
+    /// Whether `category` is allowed to be generated for `cpp_name`.
+    /// Types with no restriction configured allow every category.
+    pub fn is_category_enabled(&self, cpp_name: &str, category: Category) -> bool {
+        match self.category_restrictions.get(cpp_name) {
+            None => true,
+            Some(allowed) => allowed.contains(&category),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unblocked_type_has_no_orphan_reason() {
+        let db = TypeDatabase::default();
+        assert_eq!(db.reason_type_is_orphaned_by_block("Baz"), None);
+    }
+
+    #[test]
+    fn orphan_reason_is_keyed_by_the_dependent_type_not_the_blocked_api() {
+        let mut db = TypeDatabase::default();
+        db.block("Foo::bar", Some("not thread-safe"));
+        db.note_blocked_api_deps("Foo::bar", vec!["Baz".to_string()]);
+
+        // The type that got orphaned (`Baz`) was never itself blocked...
+        assert_eq!(db.reason_for_block("Baz"), None);
+        // ...but we can still explain why it disappeared.
+        assert_eq!(
+            db.reason_type_is_orphaned_by_block("Baz"),
+            Some("not thread-safe")
+        );
+    }
+
+    #[test]
+    fn category_restriction_defaults_to_allow_everything() {
+        let mut db = TypeDatabase::default();
+        assert!(db.is_category_enabled("Foo", Category::Methods));
+        db.restrict_to_categories("Foo", vec![Category::Constructors]);
+        assert!(db.is_category_enabled("Foo", Category::Constructors));
+        assert!(!db.is_category_enabled("Foo", Category::Methods));
+        // An unrelated type is still unrestricted.
+        assert!(db.is_category_enabled("Bar", Category::Methods));
+    }
+}